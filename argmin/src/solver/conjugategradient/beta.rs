@@ -0,0 +1,340 @@
+// Copyright 2018-2022 argmin developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! # References:
+//!
+//! \[0\] Jorge Nocedal and Stephen J. Wright (2006). Numerical Optimization.
+//! Springer. ISBN 0-387-30303-0.
+
+use crate::core::{ArgminFloat, ArgminNLCGBetaUpdate};
+use argmin_math::{ArgminDot, ArgminMul, ArgminNorm, ArgminSub};
+#[cfg(feature = "serde1")]
+use serde::{Deserialize, Serialize};
+
+/// Trait for preconditioners used in [`NonlinearConjugateGradient`](super::NonlinearConjugateGradient).
+///
+/// A preconditioner approximates the action of the inverse Hessian on a gradient, which can
+/// dramatically accelerate convergence on ill-conditioned problems.
+pub trait ArgminPreconditioner<T> {
+    /// Apply the preconditioner to `x`
+    fn precondition(&self, x: &T) -> T;
+}
+
+/// The identity preconditioner, used by default when no preconditioner has been set via
+/// [`NonlinearConjugateGradient::precondition`](super::NonlinearConjugateGradient::precondition).
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct NoPreconditioner {}
+
+impl<T: Clone> ArgminPreconditioner<T> for NoPreconditioner {
+    fn precondition(&self, x: &T) -> T {
+        x.clone()
+    }
+}
+
+/// Polak and Ribiere (PR) beta update.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct PolakRibiere {}
+
+impl PolakRibiere {
+    /// Constructor
+    pub fn new() -> Self {
+        PolakRibiere {}
+    }
+}
+
+impl Default for PolakRibiere {
+    fn default() -> Self {
+        PolakRibiere::new()
+    }
+}
+
+impl<T, F> ArgminNLCGBetaUpdate<T, F> for PolakRibiere
+where
+    T: ArgminDot<T, F> + ArgminSub<T, T>,
+    F: ArgminFloat,
+{
+    fn update(&self, grad: &T, new_grad: &T, _prev_p: &T, m_grad: &T, m_new_grad: &T) -> F {
+        // Preconditioned Polak-Ribiere: `beta = (M g_{k+1}) . (g_{k+1} - g_k) / (g_k . (M g_k))`.
+        // With the identity preconditioner, `m_grad == grad` and `m_new_grad == new_grad`, which
+        // reduces to the classic PR update.
+        m_new_grad.dot(&new_grad.sub(grad)) / grad.dot(m_grad)
+    }
+}
+
+/// Fletcher and Reeves (FR) beta update.
+///
+/// `beta = \|g_{k+1}\|^2 / \|g_k\|^2`
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct FletcherReeves {}
+
+impl FletcherReeves {
+    /// Constructor
+    pub fn new() -> Self {
+        FletcherReeves {}
+    }
+}
+
+impl<T, F> ArgminNLCGBetaUpdate<T, F> for FletcherReeves
+where
+    T: ArgminDot<T, F>,
+    F: ArgminFloat,
+{
+    fn update(&self, grad: &T, new_grad: &T, _prev_p: &T, m_grad: &T, m_new_grad: &T) -> F {
+        // Preconditioned FR: substitute `M*g` for `g` in both inner products. With the identity
+        // preconditioner, `m_grad == grad` and `m_new_grad == new_grad`, which reduces to the
+        // classic FR update `beta = \|g_{k+1}\|^2 / \|g_k\|^2`.
+        let denom = grad.dot(m_grad);
+        if denom.abs() < F::epsilon() {
+            return F::from_f64(0.0).unwrap();
+        }
+        new_grad.dot(m_new_grad) / denom
+    }
+}
+
+/// Hestenes and Stiefel (HS) beta update.
+///
+/// `beta = g_{k+1}.(g_{k+1} - g_k) / (d_k.(g_{k+1} - g_k))`
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct HestenesStiefel {}
+
+impl HestenesStiefel {
+    /// Constructor
+    pub fn new() -> Self {
+        HestenesStiefel {}
+    }
+}
+
+impl<T, F> ArgminNLCGBetaUpdate<T, F> for HestenesStiefel
+where
+    T: ArgminDot<T, F> + ArgminSub<T, T>,
+    F: ArgminFloat,
+{
+    fn update(&self, grad: &T, new_grad: &T, prev_p: &T, _m_grad: &T, m_new_grad: &T) -> F {
+        // Preconditioned HS: substitute `M*g_{k+1}` for `g_{k+1}` in the leading inner product.
+        let y_k = new_grad.sub(grad);
+        let denom = prev_p.dot(&y_k);
+        if denom.abs() < F::epsilon() {
+            return F::from_f64(0.0).unwrap();
+        }
+        m_new_grad.dot(&y_k) / denom
+    }
+}
+
+/// Dai and Yuan (DY) beta update.
+///
+/// `beta = \|g_{k+1}\|^2 / (d_k.(g_{k+1} - g_k))`
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct DaiYuan {}
+
+impl DaiYuan {
+    /// Constructor
+    pub fn new() -> Self {
+        DaiYuan {}
+    }
+}
+
+impl<T, F> ArgminNLCGBetaUpdate<T, F> for DaiYuan
+where
+    T: ArgminDot<T, F> + ArgminSub<T, T>,
+    F: ArgminFloat,
+{
+    fn update(&self, grad: &T, new_grad: &T, prev_p: &T, _m_grad: &T, m_new_grad: &T) -> F {
+        // Preconditioned DY: substitute `M*g_{k+1}` for one `g_{k+1}` in the numerator.
+        let y_k = new_grad.sub(grad);
+        let denom = prev_p.dot(&y_k);
+        if denom.abs() < F::epsilon() {
+            return F::from_f64(0.0).unwrap();
+        }
+        m_new_grad.dot(new_grad) / denom
+    }
+}
+
+/// Truncated Polak-Ribiere (PR+) beta update.
+///
+/// `beta = max(0, beta_PR)`, which prevents the search direction from being updated with a
+/// negative weight on the previous direction.
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct PolakRibierePlus {
+    pr: PolakRibiere,
+}
+
+impl PolakRibierePlus {
+    /// Constructor
+    pub fn new() -> Self {
+        PolakRibierePlus {
+            pr: PolakRibiere::new(),
+        }
+    }
+}
+
+impl<T, F> ArgminNLCGBetaUpdate<T, F> for PolakRibierePlus
+where
+    T: ArgminDot<T, F> + ArgminSub<T, T>,
+    F: ArgminFloat,
+{
+    fn update(&self, grad: &T, new_grad: &T, prev_p: &T, m_grad: &T, m_new_grad: &T) -> F {
+        self.pr
+            .update(grad, new_grad, prev_p, m_grad, m_new_grad)
+            .max(F::from_f64(0.0).unwrap())
+    }
+}
+
+/// Hager-Zhang (CG_DESCENT) beta update.
+///
+/// A numerically robust update which guarantees a descent direction
+/// independent of the accuracy of the line search. The update is truncated
+/// from below as `beta = max(beta_HZ, eta_k)` with
+/// `eta_k = -1 / (\|d_k\| * min(eta, \|g_k\|))`. When `d_k \cdot y_k` is
+/// (close to) zero, `beta = 0` is returned, which restarts the method.
+///
+/// # References:
+///
+/// \[0\] William W. Hager and Hongchao Zhang (2005). A new conjugate gradient
+/// method with guaranteed descent and an efficient line search. SIAM Journal
+/// on Optimization, 16(1), 170-192.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub struct HagerZhang<F> {
+    /// Constant `eta` in the truncation bound `eta_k = -1 / (\|d_k\| * min(eta, \|g_k\|))`
+    eta: F,
+}
+
+impl<F: ArgminFloat> HagerZhang<F> {
+    /// Constructor
+    pub fn new() -> Self {
+        HagerZhang {
+            eta: F::from_f64(0.01).unwrap(),
+        }
+    }
+
+    /// Set the constant `eta` used in the truncation bound `eta_k` (default: `0.01`)
+    #[must_use]
+    pub fn eta(mut self, eta: F) -> Self {
+        self.eta = eta;
+        self
+    }
+}
+
+impl<F: ArgminFloat> Default for HagerZhang<F> {
+    fn default() -> Self {
+        HagerZhang::new()
+    }
+}
+
+impl<T, F> ArgminNLCGBetaUpdate<T, F> for HagerZhang<F>
+where
+    T: ArgminDot<T, F> + ArgminSub<T, T> + ArgminMul<F, T> + ArgminNorm<F>,
+    F: ArgminFloat,
+{
+    fn update(&self, grad: &T, new_grad: &T, prev_p: &T, _m_grad: &T, m_new_grad: &T) -> F {
+        // Preconditioned Hager-Zhang: substitute `M*g_{k+1}` for `g_{k+1}` in the final inner
+        // product, leaving `y_k = g_{k+1} - g_k` and the truncation term unconditioned.
+        let y_k = new_grad.sub(grad);
+        let dk_dot_yk = prev_p.dot(&y_k);
+
+        if dk_dot_yk.abs() < F::epsilon() {
+            // `d_k . y_k` is (close to) zero: fall back to a restart to avoid
+            // dividing by (close to) zero.
+            return F::from_f64(0.0).unwrap();
+        }
+
+        let yk_norm_sqr = y_k.dot(&y_k);
+        let beta_hz = y_k
+            .sub(&prev_p.mul(&(F::from_f64(2.0).unwrap() * yk_norm_sqr / dk_dot_yk)))
+            .dot(m_new_grad)
+            / dk_dot_yk;
+
+        let eta_k = F::from_f64(-1.0).unwrap() / (prev_p.norm() * self.eta.min(grad.norm()));
+
+        beta_hz.max(eta_k)
+    }
+}
+
+/// Runtime-selectable beta update method.
+///
+/// Allows a single [`NonlinearConjugateGradient`](super::NonlinearConjugateGradient) instance to
+/// switch between beta update strategies without changing its generic `B` parameter, which is
+/// convenient for experimentation and benchmarking across methods.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
+pub enum BetaMethod<F> {
+    /// Polak-Ribiere
+    PR(PolakRibiere),
+    /// Truncated Polak-Ribiere (PR+)
+    PRPlus(PolakRibierePlus),
+    /// Fletcher-Reeves
+    FR(FletcherReeves),
+    /// Hestenes-Stiefel
+    HS(HestenesStiefel),
+    /// Dai-Yuan
+    DY(DaiYuan),
+    /// Hager-Zhang (CG_DESCENT)
+    HZ(HagerZhang<F>),
+}
+
+impl<T, F> ArgminNLCGBetaUpdate<T, F> for BetaMethod<F>
+where
+    T: ArgminDot<T, F> + ArgminSub<T, T> + ArgminMul<F, T> + ArgminNorm<F>,
+    F: ArgminFloat,
+{
+    fn update(&self, grad: &T, new_grad: &T, prev_p: &T, m_grad: &T, m_new_grad: &T) -> F {
+        match self {
+            BetaMethod::PR(m) => m.update(grad, new_grad, prev_p, m_grad, m_new_grad),
+            BetaMethod::PRPlus(m) => m.update(grad, new_grad, prev_p, m_grad, m_new_grad),
+            BetaMethod::FR(m) => m.update(grad, new_grad, prev_p, m_grad, m_new_grad),
+            BetaMethod::HS(m) => m.update(grad, new_grad, prev_p, m_grad, m_new_grad),
+            BetaMethod::DY(m) => m.update(grad, new_grad, prev_p, m_grad, m_new_grad),
+            BetaMethod::HZ(m) => m.update(grad, new_grad, prev_p, m_grad, m_new_grad),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hager_zhang_matches_canonical_value() {
+        let grad = vec![1.0, 0.0];
+        let new_grad = vec![0.0, 1.0];
+        let prev_p = vec![-1.0, 0.0];
+
+        let beta = HagerZhang::new().update(&grad, &new_grad, &prev_p, &grad, &new_grad);
+        assert!((beta - 1.0f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn hager_zhang_restarts_when_dk_dot_yk_is_zero() {
+        let grad = vec![1.0, 0.0];
+        let new_grad = vec![1.0, 0.0];
+        let prev_p = vec![1.0, 1.0];
+
+        let beta = HagerZhang::new().update(&grad, &new_grad, &prev_p, &grad, &new_grad);
+        assert_eq!(beta, 0.0);
+    }
+
+    #[test]
+    fn polak_ribiere_plus_clamps_negative_beta_to_zero() {
+        // `grad` and `new_grad` are chosen so that the classic PR update is negative.
+        let grad = vec![1.0, 0.0];
+        let new_grad = vec![0.5, 0.0];
+        let prev_p = vec![-1.0, 0.0];
+
+        let pr_beta = PolakRibiere::new().update(&grad, &new_grad, &prev_p, &grad, &new_grad);
+        assert!(pr_beta < 0.0);
+
+        let pr_plus_beta =
+            PolakRibierePlus::new().update(&grad, &new_grad, &prev_p, &grad, &new_grad);
+        assert_eq!(pr_plus_beta, 0.0);
+    }
+}
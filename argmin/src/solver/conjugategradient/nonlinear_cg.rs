@@ -13,7 +13,9 @@
 use crate::core::{
     ArgminFloat, ArgminIterData, ArgminKV, ArgminLineSearch, ArgminNLCGBetaUpdate, ArgminOp,
     ArgminResult, Error, Executor, IterState, OpWrapper, SerializeAlias, Solver, State,
+    TerminationReason,
 };
+use crate::solver::conjugategradient::beta::{ArgminPreconditioner, NoPreconditioner};
 use argmin_math::{ArgminAdd, ArgminDot, ArgminMul, ArgminNorm};
 #[cfg(feature = "serde1")]
 use serde::{Deserialize, Serialize};
@@ -27,7 +29,7 @@ use serde::{Deserialize, Serialize};
 /// Springer. ISBN 0-387-30303-0.
 #[derive(Clone)]
 #[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
-pub struct NonlinearConjugateGradient<P, L, B, F> {
+pub struct NonlinearConjugateGradient<P, L, B, F, M = NoPreconditioner> {
     /// p
     p: Option<P>,
     /// beta
@@ -40,9 +42,17 @@ pub struct NonlinearConjugateGradient<P, L, B, F> {
     restart_iter: u64,
     /// Restart based on orthogonality
     restart_orthogonality: Option<F>,
+    /// Restart based on the Powell sufficient descent condition
+    restart_powell: bool,
+    /// Sufficient descent factor `c` used by the Powell restart condition
+    restart_powell_factor: F,
+    /// Preconditioner
+    precond: M,
+    /// Relative gradient norm tolerance for the intrinsic termination criterion
+    tol_grad: F,
 }
 
-impl<P, L, B, F> NonlinearConjugateGradient<P, L, B, F>
+impl<P, L, B, F> NonlinearConjugateGradient<P, L, B, F, NoPreconditioner>
 where
     F: ArgminFloat,
 {
@@ -55,9 +65,18 @@ where
             beta_method,
             restart_iter: std::u64::MAX,
             restart_orthogonality: None,
+            restart_powell: false,
+            restart_powell_factor: F::from_f64(0.01).unwrap(),
+            precond: NoPreconditioner {},
+            tol_grad: F::epsilon().sqrt(),
         })
     }
+}
 
+impl<P, L, B, F, M> NonlinearConjugateGradient<P, L, B, F, M>
+where
+    F: ArgminFloat,
+{
     /// Specifiy the number of iterations after which a restart should be performed
     /// This allows the algorithm to "forget" previous information which may not be helpful
     /// anymore.
@@ -80,9 +99,67 @@ where
         self.restart_orthogonality = Some(v);
         self
     }
+
+    /// Enable the Powell restart criterion.
+    /// Setting this leads to a restart (setting beta = 0) whenever the new search direction
+    /// fails the sufficient descent condition
+    ///
+    /// `\nabla f_{k+1}^T * p_{k+1} <= -c * \|\nabla f_{k+1}\|^2`
+    ///
+    /// for a small constant `c`, which would otherwise risk the direction no longer being a
+    /// descent direction.
+    #[must_use]
+    pub fn restart_powell(mut self, powell: bool) -> Self {
+        self.restart_powell = powell;
+        self
+    }
+
+    /// Set the sufficient descent factor `c` used by the Powell restart condition (default:
+    /// `1e-2`). Only has an effect when [`restart_powell`](Self::restart_powell) is enabled.
+    #[must_use]
+    pub fn restart_powell_factor(mut self, c: F) -> Self {
+        self.restart_powell_factor = c;
+        self
+    }
+
+    /// Set a preconditioner.
+    ///
+    /// `M` approximates the action of the inverse Hessian on a gradient. Preconditioning
+    /// replaces the steepest-descent seed `p = -grad` with `p = -M grad` and adjusts the beta
+    /// computation accordingly (see [`ArgminNLCGBetaUpdate`]), which can dramatically accelerate
+    /// convergence on ill-conditioned problems.
+    #[must_use]
+    pub fn precondition<M2>(self, precond: M2) -> NonlinearConjugateGradient<P, L, B, F, M2>
+    where
+        M2: ArgminPreconditioner<P>,
+    {
+        NonlinearConjugateGradient {
+            p: self.p,
+            beta: self.beta,
+            linesearch: self.linesearch,
+            beta_method: self.beta_method,
+            restart_iter: self.restart_iter,
+            restart_orthogonality: self.restart_orthogonality,
+            restart_powell: self.restart_powell,
+            restart_powell_factor: self.restart_powell_factor,
+            precond,
+            tol_grad: self.tol_grad,
+        }
+    }
+
+    /// Set the relative gradient norm tolerance.
+    ///
+    /// The solver terminates once `\|new_grad\| <= tol * max(1, \|x_{k+1}\|)` (default:
+    /// `sqrt(EPSILON)`), giving an intrinsic first-order optimality stopping criterion in
+    /// addition to any termination policy configured on the [`Executor`](crate::core::Executor).
+    #[must_use]
+    pub fn with_tolerance_grad(mut self, tol: F) -> Self {
+        self.tol_grad = tol;
+        self
+    }
 }
 
-impl<O, P, L, B, F> Solver<IterState<O>> for NonlinearConjugateGradient<P, L, B, F>
+impl<O, P, L, B, F, M> Solver<IterState<O>> for NonlinearConjugateGradient<P, L, B, F, M>
 where
     O: ArgminOp<Param = P, Output = F, Float = F>,
     P: Clone
@@ -93,6 +170,7 @@ where
         + ArgminNorm<O::Float>,
     L: Clone + ArgminLineSearch<O::Param, O::Float> + Solver<IterState<O>>,
     B: ArgminNLCGBetaUpdate<O::Param, O::Float>,
+    M: ArgminPreconditioner<O::Param>,
     F: ArgminFloat,
 {
     const NAME: &'static str = "Nonlinear Conjugate Gradient";
@@ -105,7 +183,8 @@ where
         let param = state.take_param().unwrap();
         let cost = op.apply(&param)?;
         let grad = op.gradient(&param)?;
-        self.p = Some(grad.mul(&(F::from_f64(-1.0).unwrap())));
+        let m_grad = self.precond.precondition(&grad);
+        self.p = Some(m_grad.mul(&(F::from_f64(-1.0).unwrap())));
         Ok(Some(
             ArgminIterData::new().param(param).cost(cost).grad(grad),
         ))
@@ -143,6 +222,8 @@ where
 
         // Update of beta
         let new_grad = op.gradient(&xk1)?;
+        let m_grad = self.precond.precondition(&grad);
+        let m_new_grad = self.precond.precondition(&new_grad);
 
         let restart_orthogonality = match self.restart_orthogonality {
             Some(v) => new_grad.dot(&grad).abs() / new_grad.norm().powi(2) >= v,
@@ -155,30 +236,70 @@ where
         if restart_iter || restart_orthogonality {
             self.beta = F::from_f64(0.0).unwrap();
         } else {
-            self.beta = self.beta_method.update(&grad, &new_grad, p);
+            self.beta = self
+                .beta_method
+                .update(&grad, &new_grad, p, &m_grad, &m_new_grad);
         }
 
         // Update of p
-        self.p = Some(
-            new_grad
-                .mul(&(F::from_f64(-1.0).unwrap()))
-                .add(&p.mul(&self.beta)),
-        );
+        let neg_m_new_grad = m_new_grad.mul(&(F::from_f64(-1.0).unwrap()));
+        let mut p_new = neg_m_new_grad.add(&p.mul(&self.beta));
+
+        // Powell restart: discard beta if the resulting direction is not a sufficient descent
+        // direction anymore.
+        let restart_powell = self.restart_powell
+            && !sufficient_descent(&new_grad, &p_new, self.restart_powell_factor);
+        if restart_powell {
+            self.beta = F::from_f64(0.0).unwrap();
+            p_new = neg_m_new_grad;
+        }
+
+        self.p = Some(p_new);
 
         // Housekeeping
         let cost = op.apply(&xk1)?;
 
-        Ok(ArgminIterData::new()
+        // Intrinsic termination on the (relative) gradient norm, mirroring how line searches
+        // report their own convergence instead of relying solely on the executor's termination
+        // policy.
+        let terminated = gradient_norm_converged(&new_grad, &xk1, self.tol_grad);
+
+        let mut out = ArgminIterData::new()
             .param(xk1)
             .cost(cost)
             .grad(new_grad)
             .kv(make_kv!("beta" => self.beta;
              "restart_iter" => restart_iter;
              "restart_orthogonality" => restart_orthogonality;
-            )))
+             "restart_powell" => restart_powell;
+            ));
+
+        if terminated {
+            out = out.termination_reason(TerminationReason::TargetPrecisionReached);
+        }
+
+        Ok(out)
     }
 }
 
+/// Powell sufficient descent condition: `new_grad . p_new <= -c * \|new_grad\|^2`.
+fn sufficient_descent<T, F>(new_grad: &T, p_new: &T, c: F) -> bool
+where
+    T: ArgminDot<T, F>,
+    F: ArgminFloat,
+{
+    new_grad.dot(p_new) <= -c * new_grad.dot(new_grad)
+}
+
+/// Relative gradient norm termination criterion: `\|new_grad\| <= tol * max(1, \|xk1\|)`.
+fn gradient_norm_converged<T, F>(new_grad: &T, xk1: &T, tol: F) -> bool
+where
+    T: ArgminNorm<F>,
+    F: ArgminFloat,
+{
+    new_grad.norm() <= tol * F::from_f64(1.0).unwrap().max(xk1.norm())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,4 +317,34 @@ mod tests {
             f64
         >
     );
-}
\ No newline at end of file
+
+    #[test]
+    fn sufficient_descent_rejects_non_descent_direction() {
+        // `p_new` points in the same direction as `new_grad`, i.e. uphill: never sufficient
+        // descent regardless of `c`.
+        let new_grad = vec![1.0, 0.0];
+        let p_new = vec![1.0, 0.0];
+        assert!(!sufficient_descent(&new_grad, &p_new, 0.01));
+    }
+
+    #[test]
+    fn sufficient_descent_accepts_steepest_descent_direction() {
+        let new_grad = vec![1.0, 0.0];
+        let p_new = vec![-1.0, 0.0];
+        assert!(sufficient_descent(&new_grad, &p_new, 0.01));
+    }
+
+    #[test]
+    fn gradient_norm_converged_fires_at_tolerance() {
+        let new_grad = vec![0.01, 0.0];
+        let xk1 = vec![0.0, 0.0];
+        assert!(gradient_norm_converged(&new_grad, &xk1, 0.01));
+    }
+
+    #[test]
+    fn gradient_norm_converged_does_not_fire_above_tolerance() {
+        let new_grad = vec![1.0, 0.0];
+        let xk1 = vec![0.0, 0.0];
+        assert!(!gradient_norm_converged(&new_grad, &xk1, 0.01));
+    }
+}